@@ -1,14 +1,62 @@
+use std::collections::{ BTreeMap, BTreeSet, VecDeque };
+
 use minifb::{ Key, Window, WindowOptions, ScaleMode };
 use anyhow;
 
 const WIDTH: usize = 640;
 const HEIGHT: usize = 360;
-const GROUND_DRAG_FACTOR: f64 = 0.1;
 const GRAVITY: f64 = 0.5;
 const AIR_RESISTANCE_FACTOR: f64 = 0.01;
 const DT: f64 = 1.0;
 const FPS: u64 = 60;
 
+// how many past frames of world snapshots / confirmed inputs are kept
+// around for rollback. at 60 FPS this is two seconds of history.
+const HISTORY_FRAMES: u64 = 120;
+
+// keys that game objects actually react to. only these get a bit in
+// `InputState`, so the recorded/replayed input history stays tiny.
+const RELEVANT_KEYS: [Key; 3] = [Key::A, Key::D, Key::W];
+
+// a single frame's input, bit-packed so it's cheap to record and replay.
+// capturing it once per frame (instead of reading `window.get_keys()`
+// from inside the simulation step) is what makes `Engine::advance`
+// deterministic given (state, inputs).
+#[derive(Clone, Copy, Default)]
+pub struct InputState {
+    bits: u32,
+}
+
+impl InputState {
+    pub fn capture(keys: &[Key]) -> Self {
+        let mut bits = 0;
+
+        for (index, key) in RELEVANT_KEYS.iter().enumerate() {
+            if keys.contains(key) {
+                bits |= 1 << index;
+            }
+        }
+
+        Self { bits }
+    }
+
+    pub fn is_down(&self, key: Key) -> bool {
+        match RELEVANT_KEYS.iter().position(|relevant_key| *relevant_key == key) {
+            Some(index) => self.bits & (1 << index) != 0,
+            None => false,
+        }
+    }
+}
+
+// a recorded snapshot of one object's simulated state, used by
+// `Engine::save_state`/`load_state` to support rollback.
+#[derive(Clone)]
+struct ObjectState {
+    coords: XYPair,
+    velocities: XYPair,
+    angular_velocity: f64,
+}
+
 #[derive(Clone, Default)]
 pub struct XYPair {
     pub x: f64,
@@ -28,22 +76,96 @@ pub struct ObjectInfo {
 pub struct GameObjectCommon {
     pub coords: XYPair,
     pub velocities: XYPair,
+    pub angular_velocity: f64,
     pub object_info: Option<ObjectInfo>,
 }
 
 pub enum CollisionShape {
     Circle(f64),
+    Rectangle { width: f64, height: f64 },
 }
 pub enum CollisionType {
     Circle,
     Rectangle,
 }
 
+// snapshot of the material properties a collision response needs, bundled
+// together so the physics helpers don't have to take half a dozen loose
+// f64 arguments.
+struct RigidBody {
+    mass: f64,
+    moment_of_inertia: f64,
+    restitution: f64,
+    friction_coefficient: f64,
+}
+
+impl RigidBody {
+    fn capture(object: &dyn GameObject) -> Self {
+        Self {
+            mass: object.weight_factor(),
+            moment_of_inertia: object.moment_of_inertia(),
+            restitution: object.restitution(),
+            friction_coefficient: object.friction_coefficient(),
+        }
+    }
+}
+
+// -------------------------------------------------------------------------
+// entity-component scaffold
+// -------------------------------------------------------------------------
+//
+// `Engine.objects: Vec<Box<dyn GameObject>>` makes every object implement
+// drawing, physics, collision *and* input behind one dynamic-dispatch
+// trait, even if it only cares about one of those. this is a lighter,
+// parallel path: an entity is just an id, and it only pays for the
+// behavior it opts into by attaching a component. the `Engine::entity_*`
+// systems below each iterate just the storages they need, instead of going
+// through a `GameObject` vtable call per object per concern.
+//
+// `Ball` and `RectObject` stay on the legacy path for now - migrating them
+// is follow-up work, not part of adding the scaffold.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntityId(u32);
+
+pub struct Transform {
+    pub coords: XYPair,
+    pub velocities: XYPair,
+    pub angular_velocity: f64,
+    pub mass: f64,
+}
+
+pub struct Collider {
+    pub shape: CollisionShape,
+    pub restitution: f64,
+    pub friction_coefficient: f64,
+}
+
+pub struct Sprite {
+    pub raster: Vec<Vec<u32>>,
+}
+
+// lets an entity react to input without needing a full `GameObject` impl.
+pub trait InputHandler {
+    fn handle_input(&mut self, transform: &mut Transform, inputs: &InputState);
+}
+
 pub struct Engine {
     window: Option<Window>,
     buffer: Vec<u32>,
     window_size: WindowSize,
     objects: Vec<Box<dyn GameObject>>,
+    frame: u64,
+    input_log: VecDeque<(u64, InputState)>,
+    // the `Vec<Option<ObjectState>>` alongside each legacy-object snapshot
+    // mirrors `self.transforms`, so entities spawned through the
+    // entity-component path roll back too, not just `GameObject`s.
+    snapshots: VecDeque<(u64, Vec<ObjectState>, Vec<Option<ObjectState>>)>,
+
+    entity_count: u32,
+    transforms: Vec<Option<Transform>>,
+    colliders: Vec<Option<Collider>>,
+    sprites: Vec<Option<Sprite>>,
+    input_handlers: Vec<Option<Box<dyn InputHandler>>>,
 }
 
 impl Engine {
@@ -53,6 +175,15 @@ impl Engine {
             window: None,
             window_size: window_size.clone(),
             objects: Vec::new(),
+            frame: 0,
+            input_log: VecDeque::new(),
+            snapshots: VecDeque::new(),
+
+            entity_count: 0,
+            transforms: Vec::new(),
+            colliders: Vec::new(),
+            sprites: Vec::new(),
+            input_handlers: Vec::new(),
         })
     }
 
@@ -60,29 +191,162 @@ impl Engine {
         self.objects.push(Box::new(game_object))
     }
 
-    fn calc_velocities(object: &mut Box<dyn GameObject>) {
-        let mut velocities = object.common().velocities.clone();
+    // allocates a new entity id and a slot in every component storage. the
+    // entity has no behavior until components are attached to it below.
+    pub fn spawn(&mut self) -> EntityId {
+        let id = EntityId(self.entity_count);
+        self.entity_count += 1;
+
+        self.transforms.push(None);
+        self.colliders.push(None);
+        self.sprites.push(None);
+        self.input_handlers.push(None);
+
+        id
+    }
 
+    pub fn insert_transform(&mut self, id: EntityId, component: Transform) {
+        self.transforms[id.0 as usize] = Some(component);
+    }
+
+    pub fn insert_collider(&mut self, id: EntityId, component: Collider) {
+        self.colliders[id.0 as usize] = Some(component);
+    }
+
+    pub fn insert_sprite(&mut self, id: EntityId, component: Sprite) {
+        self.sprites[id.0 as usize] = Some(component);
+    }
+
+    pub fn insert_input_handler(&mut self, id: EntityId, component: impl InputHandler + 'static) {
+        self.input_handlers[id.0 as usize] = Some(Box::new(component));
+    }
+
+    // the three functions below (calc/apply_velocities, collision_checks)
+    // are thin per-object wrappers around a shared integration core so that
+    // `predict_path` can run the exact same physics off to the side without
+    // touching the live objects.
+
+    fn integrate_velocity(velocities: &mut XYPair, weight_factor: f64) {
         // apply gravity
-        let gravity = GRAVITY * object.weight_factor() * DT;
+        let gravity = GRAVITY * weight_factor * DT;
         velocities.y += gravity;
 
         // apply air drag
         velocities.x *= 1.0 - AIR_RESISTANCE_FACTOR * DT;
         velocities.y *= 1.0 - AIR_RESISTANCE_FACTOR * DT;
+    }
+
+    fn integrate_position(coords: &mut XYPair, velocities: &XYPair) {
+        coords.x += velocities.x;
+        coords.y += velocities.y;
+    }
+
+    fn resolve_window_collision(
+        coords: &mut XYPair,
+        velocities: &mut XYPair,
+        angular_velocity: &mut f64,
+        body: &RigidBody,
+        shape: &CollisionShape,
+        window_size: &WindowSize
+    ) {
+        let (width, height) = Engine::shape_extent(shape);
+        let radius = width.max(height) / 2.0;
+
+        let on_ground = coords.y + height >= (window_size.height as f64);
+
+        // x axis window collision: normal is x, tangent is y
+        if coords.x <= 0.0 {
+            coords.x = 0.0;
+            Engine::bounce_with_spin(velocities, angular_velocity, body, radius, true);
+        }
+        if coords.x + width > (window_size.width as f64) {
+            coords.x = (window_size.width as f64) - width;
+            Engine::bounce_with_spin(velocities, angular_velocity, body, radius, true);
+        }
+
+        // y axis window collision: normal is y, tangent is x
+        if coords.y - height < 0.0 {
+            coords.y = height;
+            Engine::bounce_with_spin(velocities, angular_velocity, body, radius, false);
+        }
+        if coords.y + height > (window_size.height as f64) {
+            coords.y = (window_size.height as f64) - height;
+            Engine::bounce_with_spin(velocities, angular_velocity, body, radius, false);
+
+            // once resting on the ground, couple linear and angular velocity
+            // so the object settles into rolling instead of sliding
+            if on_ground && velocities.y.abs() <= 1.0 {
+                Engine::couple_rolling(velocities, angular_velocity, radius);
+            }
+        }
+    }
+
+    // resolves a single-axis bounce as a normal impulse plus Coulomb
+    // friction at the contact point. friction couples some of the
+    // tangential linear velocity into spin (and vice versa), rather than
+    // just flipping the velocity sign like a plain reflection would.
+    fn bounce_with_spin(
+        velocities: &mut XYPair,
+        angular_velocity: &mut f64,
+        body: &RigidBody,
+        radius: f64,
+        normal_is_x: bool
+    ) {
+        let (normal_velocity, tangential_velocity) = if normal_is_x {
+            (velocities.x, velocities.y)
+        } else {
+            (velocities.y, velocities.x)
+        };
+
+        let normal_impulse = -(1.0 + body.restitution) * body.mass * normal_velocity;
+
+        // velocity of the contact point along the tangent: the linear
+        // tangential motion plus the surface speed contributed by spin
+        let contact_velocity = tangential_velocity - *angular_velocity * radius;
+        let max_friction_impulse = body.friction_coefficient * normal_impulse.abs();
+        // friction opposes the slip at the contact point, so the impulse
+        // must drive `contact_velocity` towards zero, not away from it. the
+        // impulse changes both the linear and the angular velocity, so the
+        // one that exactly stops the slip uses the effective mass of the two.
+        let effective_mass = 1.0 / (1.0 / body.mass + radius * radius / body.moment_of_inertia);
+        let tangential_impulse = (-effective_mass * contact_velocity).clamp(
+            -max_friction_impulse,
+            max_friction_impulse
+        );
+
+        let new_normal_velocity = normal_velocity + normal_impulse / body.mass;
+        let new_tangential_velocity = tangential_velocity + tangential_impulse / body.mass;
+        *angular_velocity -= tangential_impulse * radius / body.moment_of_inertia;
+
+        if normal_is_x {
+            velocities.x = new_normal_velocity;
+            velocities.y = new_tangential_velocity;
+        } else {
+            velocities.y = new_normal_velocity;
+            velocities.x = new_tangential_velocity;
+        }
+    }
+
+    // blends the linear tangential (x) velocity and angular velocity
+    // towards the pure-rolling condition v = omega * radius - the same
+    // contact-point-velocity convention `bounce_with_spin` targets.
+    fn couple_rolling(velocities: &mut XYPair, angular_velocity: &mut f64, radius: f64) {
+        let rolling_velocity = (velocities.x + *angular_velocity * radius) / 2.0;
+        velocities.x = rolling_velocity;
+        *angular_velocity = rolling_velocity / radius;
+    }
+
+    fn calc_velocities(object: &mut Box<dyn GameObject>) {
+        let weight_factor = object.weight_factor();
+        let common = object.common();
 
-        object.common().velocities = velocities;
+        Engine::integrate_velocity(&mut common.velocities, weight_factor);
     }
 
     fn apply_velocities(object: &mut Box<dyn GameObject>) {
         let common = object.common();
-        let coords = common.coords.clone();
-        let velocities = common.velocities.clone();
 
-        object.common().coords = XYPair {
-            x: coords.x + velocities.x,
-            y: coords.y + velocities.y,
-        };
+        Engine::integrate_position(&mut common.coords, &common.velocities);
     }
 
     fn update_object_info(window_size: &WindowSize, object: &mut Box<dyn GameObject>) {
@@ -92,62 +356,217 @@ impl Engine {
     }
 
     fn draw(buffer: &mut Vec<u32>, window_size: &WindowSize, object: &mut Box<dyn GameObject>) {
+        let coords = object.common().coords.clone();
+
+        // solid-color rectangles can be blitted directly, skipping the
+        // raster + draw_at round trip
+        if let (CollisionShape::Rectangle { width, height }, Some(color)) = (
+            object.collision_shape(),
+            object.fill_color(),
+        ) {
+            let size = XYPair { x: width, y: height };
+            Engine::rect(buffer, window_size.width, window_size.height, &coords, &size, color);
+            return;
+        }
+
         let raster_vecs = object.draw();
 
+        Engine::draw_at(buffer, window_size.width, window_size.height, raster_vecs, &coords);
+    }
+
+    fn collision_checks(window_size: &WindowSize, object: &mut Box<dyn GameObject>) {
+        let body = RigidBody::capture(object.as_ref());
+        let shape = object.collision_shape();
         let common = object.common();
-        let coords = &common.coords;
 
-        Engine::draw_at(buffer, window_size.width, window_size.height, raster_vecs, coords);
+        Engine::resolve_window_collision(
+            &mut common.coords,
+            &mut common.velocities,
+            &mut common.angular_velocity,
+            &body,
+            &shape,
+            window_size
+        );
     }
 
-    fn collision_checks(window_size: &WindowSize, object: &mut Box<dyn GameObject>) {
-        match object.collision_shape() {
-            CollisionShape::Circle(radius) => {
-                let mut coords = object.common().coords.clone();
-                let mut velocities = object.common().velocities.clone();
-                let diameter = 2.0 * radius;
-
-                let on_ground = if coords.y + diameter >= (window_size.height as f64) {
-                    true
-                } else {
-                    false
-                };
+    fn shape_extent(shape: &CollisionShape) -> (f64, f64) {
+        match shape {
+            CollisionShape::Circle(radius) => (2.0 * radius, 2.0 * radius),
+            CollisionShape::Rectangle { width, height } => (*width, *height),
+        }
+    }
 
-                let on_x_collision = |velocities: &mut XYPair| {
-                    velocities.x = -velocities.x * object.bounciness();
-                };
+    // fills an axis-aligned box directly into a flat pixel buffer, clipped to
+    // its bounds. used to blit solid-color rectangle objects straight onto
+    // the display buffer, instead of building a `Vec<Vec<u32>>` raster the
+    // size of the object just to hand it to `draw_at`.
+    pub fn rect(
+        buffer: &mut Vec<u32>,
+        buffer_width: usize,
+        buffer_height: usize,
+        origin: &XYPair,
+        size: &XYPair,
+        color: u32
+    ) {
+        let x0 = origin.x.max(0.0) as usize;
+        let y0 = origin.y.max(0.0) as usize;
+        let x1 = ((origin.x + size.x).max(0.0) as usize).min(buffer_width);
+        let y1 = ((origin.y + size.y).max(0.0) as usize).min(buffer_height);
+
+        for row in y0..y1 {
+            let row_start = row * buffer_width;
+            for col in x0..x1 {
+                buffer[row_start + col] = color;
+            }
+        }
+    }
 
-                let on_y_collision = |velocities: &mut XYPair| {
-                    velocities.y = -velocities.y * object.bounciness();
+    fn object_aabb(coords: &XYPair, shape: &CollisionShape) -> (XYPair, XYPair) {
+        let (width, height) = Engine::shape_extent(shape);
+        (coords.clone(), XYPair { x: coords.x + width, y: coords.y + height })
+    }
 
-                    // if we're just rolling on the ground, apply ground drag
-                    if on_ground && velocities.y.abs() <= 1.0 {
-                        velocities.x -= velocities.x * GROUND_DRAG_FACTOR;
-                    }
-                };
+    // resolves pairwise collisions between all objects, so things like
+    // paddles and blocks can actually stop a ball instead of letting it
+    // pass through. a naive check would be O(n^2), so candidate pairs are
+    // first narrowed down with a uniform grid broadphase keyed by the
+    // largest object's extent, then each candidate gets a proper AABB test.
+    fn object_collision_checks(objects: &mut [Box<dyn GameObject>]) {
+        if objects.len() < 2 {
+            return;
+        }
 
-                // x axis window collision
-                if coords.x <= 0.0 {
-                    coords.x = 0.0;
-                    on_x_collision(&mut velocities);
-                }
-                if coords.x + diameter > (window_size.width as f64) {
-                    coords.x = (window_size.width as f64) - diameter;
-                    on_x_collision(&mut velocities);
-                }
+        let aabbs: Vec<(XYPair, XYPair)> = objects
+            .iter_mut()
+            .map(|object| {
+                let coords = object.common().coords.clone();
+                Engine::object_aabb(&coords, &object.collision_shape())
+            })
+            .collect();
 
-                // y axis window collision
-                if coords.y - diameter < 0.0 {
-                    coords.y = diameter;
-                    on_y_collision(&mut velocities);
+        let cell_size = aabbs
+            .iter()
+            .map(|(min, max)| (max.x - min.x).max(max.y - min.y))
+            .fold(1.0_f64, f64::max);
+
+        let cell_of = |value: f64| (value / cell_size).floor() as i64;
+
+        // `BTreeMap`/`BTreeSet` (rather than their hash-based counterparts)
+        // give a fixed iteration order, so candidate pairs that share an
+        // object are always resolved in the same sequence across runs -
+        // required for `advance` to stay deterministic given (state, inputs).
+        let mut grid: BTreeMap<(i64, i64), Vec<usize>> = BTreeMap::new();
+        for (index, (min, max)) in aabbs.iter().enumerate() {
+            for cx in cell_of(min.x)..=cell_of(max.x) {
+                for cy in cell_of(min.y)..=cell_of(max.y) {
+                    grid.entry((cx, cy)).or_default().push(index);
                 }
-                if coords.y + diameter > (window_size.height as f64) {
-                    coords.y = (window_size.height as f64) - diameter;
-                    on_y_collision(&mut velocities);
+            }
+        }
+
+        let mut candidate_pairs: BTreeSet<(usize, usize)> = BTreeSet::new();
+        for bucket in grid.values() {
+            for a in 0..bucket.len() {
+                for b in (a + 1)..bucket.len() {
+                    candidate_pairs.insert((bucket[a].min(bucket[b]), bucket[a].max(bucket[b])));
                 }
+            }
+        }
+
+        for (i, j) in candidate_pairs {
+            Engine::resolve_object_pair(objects, i, j);
+        }
+    }
+
+    // narrowphase: re-reads live coords (they may have moved from an
+    // earlier pair resolved this same frame) and separates + bounces the
+    // pair along whichever axis has the smaller penetration.
+    fn resolve_object_pair(objects: &mut [Box<dyn GameObject>], i: usize, j: usize) {
+        let (left, right) = objects.split_at_mut(j);
+        let a = &mut left[i];
+        let b = &mut right[0];
+
+        let a_static = a.is_static();
+        let b_static = b.is_static();
 
-                object.common().coords = coords;
-                object.common().velocities = velocities;
+        if a_static && b_static {
+            return;
+        }
+
+        let a_coords = a.common().coords.clone();
+        let b_coords = b.common().coords.clone();
+        let (a_min, a_max) = Engine::object_aabb(&a_coords, &a.collision_shape());
+        let (b_min, b_max) = Engine::object_aabb(&b_coords, &b.collision_shape());
+
+        let overlap_x = a_max.x.min(b_max.x) - a_min.x.max(b_min.x);
+        let overlap_y = a_max.y.min(b_max.y) - a_min.y.max(b_min.y);
+
+        if overlap_x <= 0.0 || overlap_y <= 0.0 {
+            // broadphase put them in the same cell, but they don't actually overlap
+            return;
+        }
+
+        let bounciness = a.bounciness().min(b.bounciness());
+        let a_vel = a.common().velocities.clone();
+        let b_vel = b.common().velocities.clone();
+
+        if overlap_x < overlap_y {
+            // Left/Right hit. a static partner has no velocity of its own to
+            // take on, so the dynamic side reflects off of it instead of
+            // picking up the (zero) static velocity and stopping dead.
+            let a_pushes_left = a_coords.x < b_coords.x;
+            Engine::separate_axis(a, b, overlap_x, a_pushes_left, true);
+
+            if !a_static {
+                a.common().velocities.x = if b_static { -a_vel.x * bounciness } else { b_vel.x * bounciness };
+            }
+            if !b_static {
+                b.common().velocities.x = if a_static { -b_vel.x * bounciness } else { a_vel.x * bounciness };
+            }
+        } else {
+            // Top/Bottom hit
+            let a_pushes_up = a_coords.y < b_coords.y;
+            Engine::separate_axis(a, b, overlap_y, a_pushes_up, false);
+
+            if !a_static {
+                a.common().velocities.y = if b_static { -a_vel.y * bounciness } else { b_vel.y * bounciness };
+            }
+            if !b_static {
+                b.common().velocities.y = if a_static { -b_vel.y * bounciness } else { a_vel.y * bounciness };
+            }
+        }
+    }
+
+    // moves the non-static side(s) of the pair apart along `axis_is_x`.
+    // a static partner absorbs none of the separation, so the dynamic one
+    // gets pushed out by the full penetration depth instead of half.
+    fn separate_axis(
+        a: &mut Box<dyn GameObject>,
+        b: &mut Box<dyn GameObject>,
+        overlap: f64,
+        a_is_lower: bool,
+        axis_is_x: bool
+    ) {
+        let a_static = a.is_static();
+        let b_static = b.is_static();
+
+        let (a_push, b_push) = if a_static || b_static { (overlap, overlap) } else { (overlap / 2.0, overlap / 2.0) };
+
+        let a_sign = if a_is_lower { -1.0 } else { 1.0 };
+        let b_sign = -a_sign;
+
+        if !a_static {
+            if axis_is_x {
+                a.common().coords.x += a_sign * a_push;
+            } else {
+                a.common().coords.y += a_sign * a_push;
+            }
+        }
+        if !b_static {
+            if axis_is_x {
+                b.common().coords.x += b_sign * b_push;
+            } else {
+                b.common().coords.y += b_sign * b_push;
             }
         }
     }
@@ -183,6 +602,286 @@ impl Engine {
         }
     }
 
+    // entity-component systems: each iterates only the storages it needs,
+    // instead of going through a `GameObject` vtable call per concern.
+
+    fn entity_physics_system(transforms: &mut [Option<Transform>]) {
+        for transform in transforms.iter_mut().flatten() {
+            Engine::integrate_velocity(&mut transform.velocities, transform.mass);
+            Engine::integrate_position(&mut transform.coords, &transform.velocities);
+        }
+    }
+
+    fn entity_collision_system(
+        window_size: &WindowSize,
+        transforms: &mut [Option<Transform>],
+        colliders: &[Option<Collider>]
+    ) {
+        for (transform, collider) in transforms.iter_mut().zip(colliders.iter()) {
+            if let (Some(transform), Some(collider)) = (transform, collider) {
+                let (width, height) = Engine::shape_extent(&collider.shape);
+                let radius = width.max(height) / 2.0;
+
+                let body = RigidBody {
+                    mass: transform.mass,
+                    moment_of_inertia: 0.5 * transform.mass * radius * radius,
+                    restitution: collider.restitution,
+                    friction_coefficient: collider.friction_coefficient,
+                };
+
+                Engine::resolve_window_collision(
+                    &mut transform.coords,
+                    &mut transform.velocities,
+                    &mut transform.angular_velocity,
+                    &body,
+                    &collider.shape,
+                    window_size
+                );
+            }
+        }
+    }
+
+    fn entity_input_system(
+        transforms: &mut [Option<Transform>],
+        input_handlers: &mut [Option<Box<dyn InputHandler>>],
+        inputs: &InputState
+    ) {
+        for (transform, handler) in transforms.iter_mut().zip(input_handlers.iter_mut()) {
+            if let (Some(transform), Some(handler)) = (transform, handler) {
+                handler.handle_input(transform, inputs);
+            }
+        }
+    }
+
+    // `window_size` is passed in by reference as a single shared resource
+    // here, rather than being cloned into every entity the way the legacy
+    // `ObjectInfo` path clones it into every `GameObject` each frame.
+    fn entity_render_system(
+        buffer: &mut Vec<u32>,
+        window_size: &WindowSize,
+        transforms: &[Option<Transform>],
+        sprites: &[Option<Sprite>]
+    ) {
+        for (transform, sprite) in transforms.iter().zip(sprites.iter()) {
+            if let (Some(transform), Some(sprite)) = (transform, sprite) {
+                Engine::draw_at(
+                    buffer,
+                    window_size.width,
+                    window_size.height,
+                    sprite.raster.clone(),
+                    &transform.coords
+                );
+            }
+        }
+    }
+
+    // simulates `steps` future frames for the object at `object_index` using
+    // the same integration as the live loop (gravity/drag, then movement,
+    // then window collision), without touching the real object's state.
+    // useful for drawing an aiming/trajectory preview.
+    pub fn predict_path(&self, object_index: usize, steps: usize) -> Vec<XYPair> {
+        let mut path = Vec::with_capacity(steps);
+
+        if let Some(object) = self.objects.get(object_index) {
+            let common = object.common_ref();
+            let mut coords = common.coords.clone();
+            let mut velocities = common.velocities.clone();
+            let mut angular_velocity = common.angular_velocity;
+            let weight_factor = object.weight_factor();
+            let body = RigidBody::capture(object.as_ref());
+            let shape = object.collision_shape();
+
+            for _ in 0..steps {
+                Engine::integrate_velocity(&mut velocities, weight_factor);
+                Engine::integrate_position(&mut coords, &velocities);
+                Engine::resolve_window_collision(
+                    &mut coords,
+                    &mut velocities,
+                    &mut angular_velocity,
+                    &body,
+                    &shape,
+                    &self.window_size
+                );
+
+                path.push(coords.clone());
+            }
+        }
+
+        path
+    }
+
+    // the deterministic simulation core: steps every object's physics
+    // exactly once given the current state and this frame's recorded
+    // inputs. no wall-clock reads, no window polling - everything it needs
+    // is passed in, which is what makes it replayable and rollback-safe.
+    fn step(&mut self, inputs: &InputState) {
+        for object in self.objects.iter_mut() {
+            // re-calculate the velocities of the object
+            Engine::calc_velocities(object);
+
+            // apply the velocities to the coordinates
+            Engine::apply_velocities(object);
+        }
+
+        // resolve collisions between objects before checking against the window,
+        // so a ball can bounce off a paddle as well as the window border
+        Engine::object_collision_checks(&mut self.objects);
+
+        for object in self.objects.iter_mut() {
+            // perform collision checks with the window
+            Engine::collision_checks(&self.window_size, object);
+
+            // update the game object's info
+            Engine::update_object_info(&self.window_size, object);
+
+            // allow the object to react to this frame's recorded input
+            object.handle_input(inputs);
+        }
+
+        // entity-component path: runs alongside the legacy objects above
+        // while they're migrated over one at a time.
+        Engine::entity_physics_system(&mut self.transforms);
+        Engine::entity_collision_system(&self.window_size, &mut self.transforms, &self.colliders);
+        Engine::entity_input_system(&mut self.transforms, &mut self.input_handlers, inputs);
+    }
+
+    // records `inputs` as the confirmed input for `frame`, then advances the
+    // simulation by exactly one fixed timestep. this is the only place the
+    // live loop should call `step` from - rollback replay calls `step`
+    // directly with already-recorded inputs instead, so it doesn't re-log
+    // frames that are already in history.
+    pub fn advance(&mut self, frame: u64, inputs: &InputState) {
+        if self.input_log.len() as u64 == HISTORY_FRAMES {
+            self.input_log.pop_front();
+        }
+        self.input_log.push_back((frame, *inputs));
+
+        self.step(inputs);
+
+        self.frame = frame + 1;
+    }
+
+    // overwrites the input already recorded for `frame` instead of
+    // appending a duplicate entry, so a late-arriving/corrected input
+    // (e.g. over the network) can be applied by a following `rollback_to`.
+    // if `frame` hasn't been logged yet, it's appended like `advance` does.
+    pub fn set_input(&mut self, frame: u64, inputs: InputState) {
+        if let Some(entry) = self.input_log.iter_mut().find(|(logged_frame, _)| *logged_frame == frame) {
+            entry.1 = inputs;
+            return;
+        }
+
+        if self.input_log.len() as u64 == HISTORY_FRAMES {
+            self.input_log.pop_front();
+        }
+        self.input_log.push_back((frame, inputs));
+    }
+
+    // snapshots every object's and entity's simulated state under the
+    // current frame number, for later rollback. older snapshots are
+    // dropped once `HISTORY_FRAMES` have been recorded. if a snapshot for
+    // the current frame already exists (a replay from `rollback_to`
+    // re-simulated it), it's overwritten instead of appended as a
+    // duplicate.
+    pub fn save_state(&mut self) {
+        let snapshot: Vec<ObjectState> = self.objects
+            .iter_mut()
+            .map(|object| {
+                let common = object.common();
+                ObjectState {
+                    coords: common.coords.clone(),
+                    velocities: common.velocities.clone(),
+                    angular_velocity: common.angular_velocity,
+                }
+            })
+            .collect();
+
+        let entity_snapshot: Vec<Option<ObjectState>> = self.transforms
+            .iter()
+            .map(|transform| {
+                transform.as_ref().map(|transform| ObjectState {
+                    coords: transform.coords.clone(),
+                    velocities: transform.velocities.clone(),
+                    angular_velocity: transform.angular_velocity,
+                })
+            })
+            .collect();
+
+        match self.snapshots.iter_mut().find(|(snapshot_frame, _, _)| *snapshot_frame == self.frame) {
+            Some(entry) => {
+                entry.1 = snapshot;
+                entry.2 = entity_snapshot;
+            }
+            None => {
+                if self.snapshots.len() as u64 == HISTORY_FRAMES {
+                    self.snapshots.pop_front();
+                }
+                self.snapshots.push_back((self.frame, snapshot, entity_snapshot));
+            }
+        }
+    }
+
+    // restores the world exactly as it was at `frame`, if a snapshot for it
+    // is still in history. returns whether a matching snapshot was found.
+    pub fn load_state(&mut self, frame: u64) -> bool {
+        let snapshot = self.snapshots
+            .iter()
+            .find(|(snapshot_frame, _, _)| *snapshot_frame == frame)
+            .map(|(_, state, entity_state)| (state.clone(), entity_state.clone()));
+
+        match snapshot {
+            Some((state, entity_state)) => {
+                for (object, state) in self.objects.iter_mut().zip(state.iter()) {
+                    let common = object.common();
+                    common.coords = state.coords.clone();
+                    common.velocities = state.velocities.clone();
+                    common.angular_velocity = state.angular_velocity;
+                }
+
+                for (transform, state) in self.transforms.iter_mut().zip(entity_state.iter()) {
+                    if let (Some(transform), Some(state)) = (transform, state) {
+                        transform.coords = state.coords.clone();
+                        transform.velocities = state.velocities.clone();
+                        transform.angular_velocity = state.angular_velocity;
+                    }
+                }
+
+                self.frame = frame;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // restores the snapshot at `frame`, then re-simulates forward using the
+    // inputs recorded since then, so a late-arriving/corrected input (e.g.
+    // over the network) is applied exactly where it happened. this is the
+    // core of rollback netcode and deterministic replays.
+    pub fn rollback_to(&mut self, frame: u64) -> bool {
+        if !self.load_state(frame) {
+            return false;
+        }
+
+        let replay_inputs: Vec<(u64, InputState)> = self.input_log
+            .iter()
+            .filter(|(logged_frame, _)| *logged_frame >= frame)
+            .cloned()
+            .collect();
+
+        for (replay_frame, inputs) in replay_inputs {
+            self.step(&inputs);
+            self.frame = replay_frame + 1;
+
+            // the snapshot for this frame was recorded against the
+            // pre-replay state; overwrite it with what actually came out of
+            // the replay, or a later rollback into this range would load
+            // stale, pre-correction data.
+            self.save_state();
+        }
+
+        true
+    }
+
     pub fn run(&mut self, window_title: &str) -> Result<(), anyhow::Error> {
         self.window = Some(
             Window::new(
@@ -203,33 +902,24 @@ impl Engine {
             self.window.as_ref().unwrap().is_open() &&
             !self.window.as_ref().unwrap().is_key_down(Key::Escape)
         {
-            let keys = self.window.as_ref().unwrap().get_keys();
+            let inputs = InputState::capture(&self.window.as_ref().unwrap().get_keys());
 
             // clear the display buffer
             self.buffer.iter_mut().for_each(|p| {
                 *p = 0;
             });
 
-            for object in self.objects.iter_mut() {
-                // re-calculate the velocities of the object
-                Engine::calc_velocities(object);
-
-                // apply the velocities to the coordinates
-                Engine::apply_velocities(object);
-
-                // perform collision checks with the window
-                Engine::collision_checks(&self.window_size, object);
-
-                // update the game object's info
-                Engine::update_object_info(&self.window_size, object);
-
-                // allow the object to react to pressed keys
-                object.handle_input(&keys);
+            let frame = self.frame;
+            self.advance(frame, &inputs);
+            self.save_state();
 
+            for object in self.objects.iter_mut() {
                 // draw the object on the buffer at it's coords
                 Engine::draw(&mut self.buffer, &self.window_size, object);
             }
 
+            Engine::entity_render_system(&mut self.buffer, &self.window_size, &self.transforms, &self.sprites);
+
             // reflect the display buffer changes
             self.window
                 .as_mut()
@@ -243,21 +933,63 @@ impl Engine {
 
 pub const DEFAULT_COLLISION_DAMPING_FACTOR: f64 = 0.8;
 pub const DEFAULT_COLLISION_DAMPING_FACTOR_RECTANGLE: f64 = 0.5;
+pub const DEFAULT_RESTITUTION: f64 = 0.6;
+pub const DEFAULT_FRICTION_COEFFICIENT: f64 = 2.0;
 
 pub trait GameObject {
     fn common(&mut self) -> &mut GameObjectCommon;
 
+    // read-only counterpart of `common`, for inspecting state (e.g.
+    // trajectory prediction) without needing a mutable borrow of the object.
+    fn common_ref(&self) -> &GameObjectCommon;
+
     fn weight_factor(&self) -> f64;
 
     fn bounciness(&self) -> f64 {
         DEFAULT_COLLISION_DAMPING_FACTOR
     }
 
+    // coefficient of restitution used by the impulse-based bounce in
+    // `Engine::bounce_with_spin` (separate from `bounciness`, which the
+    // object-to-object pass still uses).
+    fn restitution(&self) -> f64 {
+        DEFAULT_RESTITUTION
+    }
+
+    // Coulomb friction coefficient at the contact point during a bounce.
+    fn friction_coefficient(&self) -> f64 {
+        DEFAULT_FRICTION_COEFFICIENT
+    }
+
+    // rotational inertia about the object's own axis, used to turn a
+    // tangential friction impulse into a change in angular velocity.
+    // defaults to a solid disc of the object's bounding radius.
+    fn moment_of_inertia(&self) -> f64 {
+        let mass = self.weight_factor();
+        let (width, height) = Engine::shape_extent(&self.collision_shape());
+        let radius = width.max(height) / 2.0;
+
+        0.5 * mass * radius * radius
+    }
+
     fn collision_shape(&self) -> CollisionShape;
 
     fn draw(&self) -> Vec<Vec<u32>>;
 
-    fn handle_input(&mut self, _keys: &[Key]) {}
+    fn handle_input(&mut self, _inputs: &InputState) {}
+
+    // static objects (walls, paddles, ...) take part in object-to-object
+    // collisions but are never moved or bounced themselves.
+    fn is_static(&self) -> bool {
+        false
+    }
+
+    // solid-color rectangle objects can return their fill color here to let
+    // the engine blit them directly with `Engine::rect` instead of going
+    // through `draw()`'s raster.
+    fn fill_color(&self) -> Option<u32> {
+        None
+    }
 }
 
 #[derive(Clone)]
@@ -266,6 +998,25 @@ pub struct WindowSize {
     pub width: usize,
 }
 
+// rasterizes a filled circle, shared by `Ball::draw` and anything else
+// (e.g. an entity `Sprite`) that wants the same shape.
+fn circle_raster(radius: f64, color: u32) -> Vec<Vec<u32>> {
+    let diameter = radius * 2.0;
+    let mut raster = vec![vec![0; diameter as usize]; diameter as usize];
+
+    for y in 0..diameter as usize {
+        for x in 0..diameter as usize {
+            let dx = ((x as f64) - radius).abs();
+            let dy = ((y as f64) - radius).abs();
+            if (dx * dx + dy * dy).sqrt() <= radius {
+                raster[y][x] = color;
+            }
+        }
+    }
+
+    raster
+}
+
 pub struct Ball {
     radius: f64,
     diameter: f64,
@@ -279,6 +1030,10 @@ impl GameObject for Ball {
         &mut self.common
     }
 
+    fn common_ref(&self) -> &GameObjectCommon {
+        &self.common
+    }
+
     fn weight_factor(&self) -> f64 {
         0.8
     }
@@ -295,8 +1050,8 @@ impl GameObject for Ball {
         self.draw()
     }
 
-    fn handle_input(&mut self, keys: &[Key]) {
-        self.handle_input(keys)
+    fn handle_input(&mut self, inputs: &InputState) {
+        self.handle_input(inputs)
     }
 }
 
@@ -324,17 +1079,17 @@ impl Ball {
     pub const KB_X_BOOST: f64 = 0.2;
     pub const KB_Y_BOOST: f64 = 16.0;
 
-    fn handle_input(&mut self, keys: &[Key]) {
-        if keys.contains(&Key::A) {
+    fn handle_input(&mut self, inputs: &InputState) {
+        if inputs.is_down(Key::A) {
             self.common.velocities.x -= Self::KB_X_BOOST;
         }
 
-        if keys.contains(&Key::D) {
+        if inputs.is_down(Key::D) {
             self.common.velocities.x += Self::KB_X_BOOST;
         }
 
         // jump if we are on the ground AND have 0 or lesser y velocity
-        if keys.contains(&Key::W) {
+        if inputs.is_down(Key::W) {
             if let Some(info) = &self.common.object_info {
                 let window_height = info.window_size.height as f64;
                 if
@@ -348,34 +1103,115 @@ impl Ball {
     }
 
     fn draw(&self) -> Vec<Vec<u32>> {
-        let mut raster =
-            vec![
-         vec![
-             0; self.diameter as usize
-            ]; self.diameter as usize
-        ];
-
-        let h = self.radius;
-        let k = self.radius;
-
-        for y in 0..self.diameter as usize {
-            for x in 0..self.diameter as usize {
-                let dx = ((x as f64) - h).abs();
-                let dy = ((y as f64) - k).abs();
-                if (dx * dx + dy * dy).sqrt() <= self.radius {
-                    raster[y][x] = self.color;
-                }
-            }
+        circle_raster(self.radius, self.color)
+    }
+}
+
+pub struct RectObject {
+    width: f64,
+    height: f64,
+    color: u32,
+    is_static: bool,
+
+    common: GameObjectCommon,
+}
+
+impl GameObject for RectObject {
+    fn common(&mut self) -> &mut GameObjectCommon {
+        &mut self.common
+    }
+
+    fn common_ref(&self) -> &GameObjectCommon {
+        &self.common
+    }
+
+    fn weight_factor(&self) -> f64 {
+        // a static rectangle never accumulates velocity from gravity, so it
+        // just sits where it's placed instead of falling through the floor.
+        if self.is_static { 0.0 } else { 1.0 }
+    }
+
+    fn bounciness(&self) -> f64 {
+        DEFAULT_COLLISION_DAMPING_FACTOR_RECTANGLE
+    }
+
+    // mirrors `bounciness` instead of falling back to `DEFAULT_RESTITUTION`,
+    // so a rectangle bounces the same whether it hit another object or the
+    // window edge.
+    fn restitution(&self) -> f64 {
+        self.bounciness()
+    }
+
+    fn collision_shape(&self) -> CollisionShape {
+        CollisionShape::Rectangle { width: self.width, height: self.height }
+    }
+
+    fn draw(&self) -> Vec<Vec<u32>> {
+        vec![vec![self.color; self.width as usize]; self.height as usize]
+    }
+
+    fn fill_color(&self) -> Option<u32> {
+        Some(self.color)
+    }
+
+    fn is_static(&self) -> bool {
+        self.is_static
+    }
+}
+
+impl RectObject {
+    pub fn new(coords: XYPair, width: f64, height: f64, color_hex: &str) -> Self {
+        Self::new_with_static(coords, width, height, color_hex, false)
+    }
+
+    // a static `RectObject` (a paddle, platform, or block) takes part in
+    // object-to-object collisions but never falls or gets pushed around
+    // itself - see `GameObject::is_static`.
+    pub fn new_static(coords: XYPair, width: f64, height: f64, color_hex: &str) -> Self {
+        Self::new_with_static(coords, width, height, color_hex, true)
+    }
+
+    fn new_with_static(coords: XYPair, width: f64, height: f64, color_hex: &str, is_static: bool) -> Self {
+        // convert hex color to u32, or default to white
+        let color = u32::from_str_radix(&color_hex[1..], 16).unwrap_or(0xffffff);
+
+        let common = GameObjectCommon {
+            coords,
+            ..GameObjectCommon::default()
+        };
+
+        Self {
+            width,
+            height,
+            color,
+            is_static,
+
+            common,
         }
+    }
+}
 
-        raster
+// a minimal `InputHandler`, demonstrating that an entity can react to input
+// by plugging this in, without writing a full `GameObject` impl.
+struct LateralNudge {
+    boost: f64,
+}
+
+impl InputHandler for LateralNudge {
+    fn handle_input(&mut self, transform: &mut Transform, inputs: &InputState) {
+        if inputs.is_down(Key::A) {
+            transform.velocities.x -= self.boost;
+        }
+        if inputs.is_down(Key::D) {
+            transform.velocities.x += self.boost;
+        }
     }
 }
 
 fn main() -> Result<(), anyhow::Error> {
   let window_size = WindowSize {width: 800, height: 600};
   let mut engine = Engine::new(&window_size)?;
-  
+
   let radius = 24.0;
   let ball_coords = XYPair {
     x: (&window_size.width / 2) as f64 - radius,
@@ -384,5 +1220,25 @@ fn main() -> Result<(), anyhow::Error> {
   let ball = Ball::new(ball_coords, radius, "#cf5353");
 
   engine.add_game_object(ball);
+
+  // a second ball, spawned through the entity-component path instead of a
+  // `GameObject` impl, mixing physics + collision + a sprite + input
+  // handling just by attaching the components it needs.
+  let entity_radius = 16.0;
+  let entity = engine.spawn();
+  engine.insert_transform(entity, Transform {
+    coords: XYPair { x: 100.0, y: 0.0 },
+    velocities: XYPair::default(),
+    angular_velocity: 0.0,
+    mass: 0.5,
+  });
+  engine.insert_collider(entity, Collider {
+    shape: CollisionShape::Circle(entity_radius),
+    restitution: DEFAULT_RESTITUTION,
+    friction_coefficient: DEFAULT_FRICTION_COEFFICIENT,
+  });
+  engine.insert_sprite(entity, Sprite { raster: circle_raster(entity_radius, 0x53cf6a) });
+  engine.insert_input_handler(entity, LateralNudge { boost: Ball::KB_X_BOOST });
+
   engine.run("Bouncy Ball")
 }
\ No newline at end of file